@@ -1,7 +1,7 @@
 use std::io::{self, BufRead};
 use serde::{Serialize, Deserialize};
 use itertools::iproduct;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 // ----- INPUT -----
 
@@ -53,41 +53,126 @@ fn distance_between_planets(planet1: &Planet, planet2: &Planet) -> f64 {
 }
 
 fn simulate_arrivals(planet: &Planet, gamestate: &GameState) -> (usize, usize) {
+    simulate_arrivals_through(planet, gamestate, usize::MAX)
+}
+
+// Same resolution as simulate_arrivals, but only plays out ticks up to and
+// including `max_turn` so callers can ask "who holds this planet right after
+// the next threat resolves" instead of after every future wave.
+fn simulate_arrivals_through(planet: &Planet, gamestate: &GameState, max_turn: usize) -> (usize, usize) {
     let mut relevant_expeditions = gamestate.expeditions
                                         .iter()
-                                        .filter(|x| x.destination == planet.name)
+                                        .filter(|x| x.destination == planet.name && x.turns_remaining <= max_turn)
                                         .collect::<Vec<&Expedition>>();
     relevant_expeditions.sort_by_key(|x| x.turns_remaining);
     let mut owner = planet.owner.unwrap_or(0);
     let mut ship_count = planet.ship_count;
     let mut last_simulated_turn = 0;
-    for expedition in relevant_expeditions {
+
+    let mut index = 0;
+    while index < relevant_expeditions.len() {
+        let turn = relevant_expeditions[index].turns_remaining;
+        let arrivals: Vec<&Expedition> = relevant_expeditions[index..]
+            .iter()
+            .take_while(|x| x.turns_remaining == turn)
+            .copied()
+            .collect();
+        index += arrivals.len();
+
         // Account for growth
         if owner != 0 {
-            ship_count += expedition.turns_remaining - last_simulated_turn;
+            ship_count += turn - last_simulated_turn;
         }
-        last_simulated_turn = expedition.turns_remaining;
-
-        if expedition.owner == owner {
-            ship_count += expedition.ship_count;
-        } else {
-            if ship_count < expedition.ship_count {
-                owner = expedition.owner;
-                ship_count = expedition.ship_count - ship_count;
-            } else if ship_count == expedition.ship_count {
-                owner = 0;
-                ship_count = 0;
-            } else {
-                ship_count -= expedition.ship_count;
-            }
+        last_simulated_turn = turn;
+
+        // Combine every fleet arriving this turn, plus the incumbent garrison,
+        // into per-owner totals so simultaneous fleets fight as one battle.
+        let mut fleets: HashMap<usize, usize> = HashMap::new();
+        *fleets.entry(owner).or_insert(0) += ship_count;
+        for expedition in &arrivals {
+            *fleets.entry(expedition.owner).or_insert(0) += expedition.ship_count;
         }
+
+        let (winner, survivors) = resolve_fleets(&fleets);
+        owner = winner;
+        ship_count = survivors;
     }
     (owner, ship_count)
 }
 
-fn score(source: &Planet, dest: &Planet, gamestate: &GameState) -> (usize, usize) {
+// Resolves a tick's combined per-owner fleet totals into (winning owner, survivor
+// count), where owner 0 means neutral (including a tie for the largest fleet).
+fn resolve_fleets(fleets: &HashMap<usize, usize>) -> (usize, usize) {
+    let mut totals: Vec<(usize, usize)> = fleets.iter().map(|(&o, &c)| (o, c)).collect();
+    totals.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    let (winner, largest) = totals[0];
+    let second = totals.get(1).map(|x| x.1).unwrap_or(0);
+
+    if totals.len() > 1 && totals[1].1 == largest {
+        (0, 0)
+    } else {
+        (winner, largest - second)
+    }
+}
+
+// One full forward step of the rules engine: ages expeditions, grows owned
+// planets and resolves every expedition that has just arrived. Pure
+// state-in/state-out so it can be replayed for lookahead search.
+fn advance_turn(state: &GameState) -> GameState {
+    let expeditions: Vec<Expedition> = state.expeditions
+        .iter()
+        .cloned()
+        .map(|mut expedition| {
+            expedition.turns_remaining = expedition.turns_remaining.saturating_sub(1);
+            expedition
+        })
+        .collect();
+
+    let planets: Vec<Planet> = state.planets
+        .iter()
+        .map(|planet| {
+            let owner = planet.owner.unwrap_or(0);
+            let mut ship_count = planet.ship_count;
+            if planet.owner.is_some() {
+                ship_count += 1;
+            }
+
+            let arrivals: Vec<&Expedition> = expeditions
+                .iter()
+                .filter(|x| x.destination == planet.name && x.turns_remaining == 0)
+                .collect();
+
+            if arrivals.is_empty() {
+                return Planet { ship_count, ..planet.clone() };
+            }
+
+            let mut fleets: HashMap<usize, usize> = HashMap::new();
+            *fleets.entry(owner).or_insert(0) += ship_count;
+            for expedition in &arrivals {
+                *fleets.entry(expedition.owner).or_insert(0) += expedition.ship_count;
+            }
+
+            let (winner, ship_count) = resolve_fleets(&fleets);
+            Planet {
+                owner: if winner == 0 { None } else { Some(winner) },
+                ship_count,
+                ..planet.clone()
+            }
+        })
+        .collect();
+
+    let expeditions: Vec<Expedition> = expeditions
+        .into_iter()
+        .filter(|x| x.turns_remaining > 0)
+        .collect();
+
+    GameState { planets, expeditions }
+}
+
+fn score_for(player_id: usize, source: &Planet, dest: &Planet, gamestate: &GameState) -> (usize, usize) {
     let (owner, ship_count) = simulate_arrivals(dest, gamestate);
-    if (ship_count+1) >= source.ship_count || owner == 1 {
+    if (ship_count+1) >= source.ship_count || owner == player_id {
         (0, 0)
     } else {
         (
@@ -97,57 +182,552 @@ fn score(source: &Planet, dest: &Planet, gamestate: &GameState) -> (usize, usize
     }
 }
 
-// ----- NEXT MOVE -----
+// ----- SEARCH -----
 
-fn next_move(state: &GameState) -> Turn {
-    let my_planets: Vec<&Planet> = state.planets
-        .iter()
-        .filter(|x| x.owner.unwrap_or(0) == 1)
+const DEFAULT_SEARCH_DEPTH: usize = 2;
+const SEARCH_CANDIDATES: usize = 3;
+const GROWTH_BONUS: f64 = 5.0;
+
+// Applies a Turn's Moves to a state: deducts the dispatched ships from their
+// origin planets and spawns an Expedition per move with the real travel time,
+// mirroring how the rules engine turns a Turn into in-flight fleets.
+fn apply_turn(state: &GameState, player_id: usize, turn: &Turn) -> GameState {
+    let mut planets = state.planets.clone();
+    let mut expeditions = state.expeditions.clone();
+    let first_id = expeditions.iter().map(|x| x.id).max().unwrap_or(0) + 1;
+
+    for (offset, mv) in turn.moves.iter().enumerate() {
+        let origin = state.planets.iter().find(|p| p.name == mv.origin).expect("origin planet must exist");
+        let destination = state.planets.iter().find(|p| p.name == mv.destination).expect("destination planet must exist");
+        let turns_remaining = distance_between_planets(origin, destination).ceil() as usize;
+
+        if let Some(source) = planets.iter_mut().find(|p| p.name == mv.origin) {
+            source.ship_count = source.ship_count.saturating_sub(mv.ship_count);
+        }
+
+        expeditions.push(Expedition {
+            id: first_id + offset,
+            origin: mv.origin.clone(),
+            destination: mv.destination.clone(),
+            turns_remaining,
+            owner: player_id,
+            ship_count: mv.ship_count,
+        });
+    }
+
+    GameState { planets, expeditions }
+}
+
+// Candidate Turns for a player: the cheapest captures (by score_for's distance
+// * ships-needed cost), always "do nothing", and the min-cost assignment Turn
+// from assignment_turn, so the search has a small, tractable branching factor
+// instead of every possible dispatch.
+fn candidate_turns(state: &GameState, player_id: usize) -> Vec<Turn> {
+    let my_planets: Vec<&Planet> = state.planets.iter().filter(|p| p.owner == Some(player_id)).collect();
+    let other_planets: Vec<&Planet> = state.planets.iter().filter(|p| p.owner != Some(player_id)).collect();
+
+    let mut captures: Vec<(&Planet, &Planet, usize, usize)> = iproduct!(my_planets.iter(), other_planets.iter())
+        .map(|(s, d)| {
+            let (ship_count, cost) = score_for(player_id, s, d, state);
+            (*s, *d, ship_count, cost)
+        })
+        .filter(|(_, _, _, cost)| *cost != 0)
         .collect();
-    let other_planets: Vec<&Planet> = state.planets
-        .iter()
-        .filter(|x| x.owner.unwrap_or(0) != 1)
+
+    captures.sort_by_key(|(_, _, _, cost)| *cost);
+    captures.truncate(SEARCH_CANDIDATES);
+
+    let mut turns: Vec<Turn> = captures
+        .into_iter()
+        .map(|(source, dest, ship_count, _)| Turn {
+            moves: vec![Move {
+                origin: source.name.clone(),
+                destination: dest.name.clone(),
+                ship_count,
+            }],
+        })
         .collect();
 
-    if my_planets.len() == 0 || other_planets.len() == 0 {
-        Turn { moves: vec![] }
+    turns.push(Turn { moves: vec![] });
+    turns.push(assignment_turn(state, player_id));
+    turns
+}
+
+// Cost used for a (target, source) pair the Hungarian algorithm should never
+// actually pick: higher than any real distance * ships-needed cost can reach.
+const UNASSIGNED_COST: i64 = 1_000_000_000;
+
+// Solves the min-cost bipartite assignment problem (Hungarian / Kuhn-Munkres
+// algorithm) for a rows x cols cost matrix with rows <= cols, returning the
+// column assigned to each row.
+fn hungarian_assignment(cost: &[Vec<i64>]) -> Vec<usize> {
+    let n = cost.len();
+    let m = if n == 0 { 0 } else { cost[0].len() };
+    assert!(n <= m, "hungarian_assignment requires at least as many columns as rows");
+
+    let mut u = vec![0i64; n + 1];
+    let mut v = vec![0i64; m + 1];
+    let mut p = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0usize;
+        let mut minv = vec![i64::MAX; m + 1];
+        let mut used = vec![false; m + 1];
+
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = i64::MAX;
+            let mut j1 = 0usize;
+
+            for j in 1..=m {
+                if !used[j] {
+                    let cur = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                    if cur < minv[j] {
+                        minv[j] = cur;
+                        way[j] = j0;
+                    }
+                    if minv[j] < delta {
+                        delta = minv[j];
+                        j1 = j;
+                    }
+                }
+            }
+
+            for j in 0..=m {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![0usize; n];
+    for j in 1..=m {
+        if p[j] != 0 {
+            assignment[p[j] - 1] = j - 1;
+        }
+    }
+    assignment
+}
+
+// Target allocation as a min-cost assignment problem instead of a greedy
+// first-come-first-served scan: every still-capturable target is a row and
+// every owned planet a column (padded with dummy "no source available"
+// columns so there are never fewer columns than rows), and the Hungarian algorithm picks
+// whichever source should fund each target to minimize total distance *
+// ships-needed cost. Targets that land on a dummy column this round go
+// around again next round with committed ships deducted, so one source can
+// end up funding several targets in the same Turn.
+fn assignment_turn(state: &GameState, player_id: usize) -> Turn {
+    let my_planets: Vec<&Planet> = state.planets.iter().filter(|p| p.owner == Some(player_id)).collect();
+    let mut remaining_targets: Vec<&Planet> = state.planets.iter().filter(|p| p.owner != Some(player_id)).collect();
+    let mut committed: HashMap<String, usize> = HashMap::new();
+    let mut moves = vec![];
+
+    if my_planets.is_empty() {
+        return Turn { moves };
+    }
+
+    let feasible = |source: &Planet, dest: &Planet, committed: &HashMap<String, usize>| {
+        let available = source.ship_count.saturating_sub(*committed.get(&source.name).unwrap_or(&0));
+        let (ship_count, cost) = score_for(player_id, source, dest, state);
+        (ship_count, cost, cost != 0 && ship_count <= available)
+    };
+
+    loop {
+        remaining_targets.retain(|dest| {
+            my_planets.iter().any(|source| feasible(source, dest, &committed).2)
+        });
+
+        if remaining_targets.is_empty() {
+            break;
+        }
+
+        let dummy_columns = remaining_targets.len().saturating_sub(my_planets.len());
+        let cost_matrix: Vec<Vec<i64>> = remaining_targets.iter()
+            .map(|dest| {
+                let mut row: Vec<i64> = my_planets.iter()
+                    .map(|source| {
+                        let (_, cost, ok) = feasible(source, dest, &committed);
+                        if ok { cost as i64 } else { UNASSIGNED_COST }
+                    })
+                    .collect();
+                row.extend(std::iter::repeat_n(UNASSIGNED_COST, dummy_columns));
+                row
+            })
+            .collect();
+
+        let assignment = hungarian_assignment(&cost_matrix);
+
+        let mut dispatched_targets: HashSet<String> = HashSet::new();
+        for (target_index, &source_index) in assignment.iter().enumerate() {
+            if source_index >= my_planets.len() {
+                continue;
+            }
+            let source = my_planets[source_index];
+            let dest = remaining_targets[target_index];
+            let (ship_count, _, ok) = feasible(source, dest, &committed);
+            if !ok {
+                continue;
+            }
+
+            moves.push(Move {
+                origin: source.name.clone(),
+                destination: dest.name.clone(),
+                ship_count,
+            });
+            *committed.entry(source.name.clone()).or_insert(0) += ship_count;
+            dispatched_targets.insert(dest.name.clone());
+        }
+
+        if dispatched_targets.is_empty() {
+            break;
+        }
+        remaining_targets.retain(|dest| !dispatched_targets.contains(&dest.name));
+    }
+
+    Turn { moves }
+}
+
+// Board-strength heuristic used at search leaves: own ship counts (on planets
+// and in flight) plus a per-planet growth-rate bonus, minus the same for
+// every other player's planets and expeditions.
+fn evaluate(state: &GameState, player_id: usize) -> f64 {
+    let mut value = 0.0;
+
+    for planet in &state.planets {
+        let sign = match planet.owner {
+            Some(owner) if owner == player_id => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        };
+        value += sign * (planet.ship_count as f64 + GROWTH_BONUS);
+    }
+
+    for expedition in &state.expeditions {
+        let sign = if expedition.owner == player_id { 1.0 } else { -1.0 };
+        value += sign * expedition.ship_count as f64;
+    }
+
+    value
+}
+
+// The non-neutral owner with the most ships on the board, used as the
+// opponent to plan against when mirroring their turn during search.
+fn strongest_rival(state: &GameState, player_id: usize) -> Option<usize> {
+    let mut totals: HashMap<usize, usize> = HashMap::new();
+    for planet in &state.planets {
+        if let Some(owner) = planet.owner {
+            if owner != player_id {
+                *totals.entry(owner).or_insert(0) += planet.ship_count;
+            }
+        }
+    }
+    totals.into_iter().max_by_key(|(_, ships)| *ships).map(|(owner, _)| owner)
+}
+
+fn alpha_beta(
+    state: &GameState,
+    player_id: usize,
+    opponent_id: usize,
+    depth: usize,
+    mut alpha: f64,
+    mut beta: f64,
+    maximizing: bool,
+) -> f64 {
+    if depth == 0 {
+        return evaluate(state, player_id);
+    }
+
+    let acting_player = if maximizing { player_id } else { opponent_id };
+    let candidates = candidate_turns(state, acting_player);
+
+    if maximizing {
+        let mut value = f64::NEG_INFINITY;
+        for turn in candidates {
+            let next_state = advance_turn(&apply_turn(state, acting_player, &turn));
+            value = value.max(alpha_beta(&next_state, player_id, opponent_id, depth - 1, alpha, beta, false));
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        value
     } else {
-        let mut moves = vec![];
+        let mut value = f64::INFINITY;
+        for turn in candidates {
+            let next_state = advance_turn(&apply_turn(state, acting_player, &turn));
+            value = value.min(alpha_beta(&next_state, player_id, opponent_id, depth - 1, alpha, beta, true));
+            beta = beta.min(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        value
+    }
+}
+
+// Picks the candidate Turn for `player_id` whose alpha-beta-backed value is
+// highest after looking `depth` plies ahead, mirroring the opponent's best
+// response at each ply.
+fn search_best_turn(state: &GameState, player_id: usize, opponent_id: usize, depth: usize) -> Turn {
+    let mut alpha = f64::NEG_INFINITY;
+    let beta = f64::INFINITY;
+    let mut best_turn = Turn { moves: vec![] };
+    let mut best_value = f64::NEG_INFINITY;
+
+    for turn in candidate_turns(state, player_id) {
+        let next_state = advance_turn(&apply_turn(state, player_id, &turn));
+        let value = alpha_beta(&next_state, player_id, opponent_id, depth.saturating_sub(1), alpha, beta, false);
+        if value > best_value {
+            best_value = value;
+            best_turn = turn;
+        }
+        alpha = alpha.max(best_value);
+    }
+
+    best_turn
+}
+
+// Reads the search depth from a `--depth N` CLI flag, falling back to the
+// `ANNA_SEARCH_DEPTH` env var, then DEFAULT_SEARCH_DEPTH.
+fn configured_search_depth() -> usize {
+    std::env::args()
+        .collect::<Vec<String>>()
+        .windows(2)
+        .find(|window| window[0] == "--depth")
+        .and_then(|window| window[1].parse().ok())
+        .or_else(|| std::env::var("ANNA_SEARCH_DEPTH").ok().and_then(|v| v.parse().ok()))
+        .unwrap_or(DEFAULT_SEARCH_DEPTH)
+}
 
-        let mut best_move = iproduct!(my_planets.iter(), other_planets.iter())
-            .map(|(s,d)| (s, d, score(s, d, state)))
-            .filter(|(_,_,(_,sc))| *sc != 0)
-            .min_by_key(|x| (*x).2.1);
+// ----- DEFENSE -----
 
-        let mut used_planets: HashSet<String> = HashSet::new();
+fn planet_value(planet: &Planet) -> f64 {
+    planet.ship_count as f64 + GROWTH_BONUS
+}
+
+// Finds Moves that reinforce owned planets simulate_arrivals predicts we are
+// about to lose, drawn from our other planets close enough (distance <= the
+// attack's turns_remaining) to arrive before it resolves. Threatened planets
+// are defended most-valuable-first, so we save our best producers first when
+// we can't save everything.
+fn defensive_moves(state: &GameState, player_id: usize) -> Vec<Move> {
+    let my_planets: Vec<&Planet> = state.planets.iter().filter(|p| p.owner == Some(player_id)).collect();
+
+    let mut threatened: Vec<(&Planet, usize, usize)> = my_planets.iter()
+        .filter_map(|&planet| {
+            // The earliest hostile wave that takes the planet from us sets the
+            // deadline; a later, bigger wave doesn't matter if we've already
+            // been flipped by then.
+            let mut hostile_turns: Vec<usize> = state.expeditions.iter()
+                .filter(|e| e.destination == planet.name && e.owner != player_id)
+                .map(|e| e.turns_remaining)
+                .collect();
+            hostile_turns.sort();
+            hostile_turns.dedup();
+            hostile_turns.into_iter().find_map(|deadline| {
+                let (predicted_owner, predicted_ship_count) = simulate_arrivals_through(planet, state, deadline);
+                if predicted_owner == player_id {
+                    return None;
+                }
+                Some((planet, deadline, predicted_ship_count + 1))
+            })
+        })
+        .collect();
+
+    threatened.sort_by(|a, b| planet_value(b.0).partial_cmp(&planet_value(a.0)).unwrap());
+
+    let mut committed: HashMap<String, usize> = HashMap::new();
+    let mut moves = vec![];
 
-        while let Some((source, dest, (ship_count, _score))) = best_move {
+    for (planet, deadline, mut needed) in threatened {
+        let mut reinforcers: Vec<&Planet> = my_planets.iter()
+            .filter(|&&source| source.name != planet.name)
+            .filter(|&&source| distance_between_planets(source, planet).ceil() as usize <= deadline)
+            .copied()
+            .collect();
+        reinforcers.sort_by(|a, b| {
+            distance_between_planets(a, planet)
+                .partial_cmp(&distance_between_planets(b, planet))
+                .unwrap()
+        });
+
+        for source in reinforcers {
+            if needed == 0 {
+                break;
+            }
+            let available = source.ship_count.saturating_sub(*committed.get(&source.name).unwrap_or(&0));
+            if available == 0 {
+                continue;
+            }
+            let send = available.min(needed);
             moves.push(Move {
-                origin: source.name.to_string(),
-                destination: dest.name.to_string(),
-                ship_count: ship_count,
+                origin: source.name.clone(),
+                destination: planet.name.clone(),
+                ship_count: send,
             });
+            *committed.entry(source.name.clone()).or_insert(0) += send;
+            needed -= send;
+        }
+    }
+
+    moves
+}
+
+// ----- NEXT MOVE -----
+
+fn plan_turn(state: &GameState, player_id: usize, depth: usize) -> Turn {
+    let my_planets_exist = state.planets.iter().any(|p| p.owner == Some(player_id));
+    let other_planets_exist = state.planets.iter().any(|p| p.owner != Some(player_id));
+
+    if !my_planets_exist || !other_planets_exist {
+        return Turn { moves: vec![] };
+    }
+
+    let defense = defensive_moves(state, player_id);
+    let post_defense_state = apply_turn(state, player_id, &Turn { moves: defense.clone() });
+
+    let opponent_id = strongest_rival(&post_defense_state, player_id).unwrap_or(0);
+    let offense = search_best_turn(&post_defense_state, player_id, opponent_id, depth);
+
+    let mut moves = defense;
+    moves.extend(offense.moves);
+    Turn { moves }
+}
+
+// Reads our own player id from a `--player-id N` CLI flag, falling back to
+// an initial handshake line on stdin (the protocol's way of telling us which
+// seat we were assigned), so we play correctly no matter which id the engine
+// gives us instead of assuming we are always player 1.
+fn configured_player_id(args: &[String], stdin: &io::Stdin) -> usize {
+    let from_flag = args.windows(2)
+        .find(|window| window[0] == "--player-id")
+        .and_then(|window| window[1].parse().ok());
+
+    if let Some(player_id) = from_flag {
+        return player_id;
+    }
+
+    let mut handshake = String::new();
+    stdin.lock().read_line(&mut handshake).expect("Could not read handshake line");
+    handshake.trim().parse().expect("Expected a player id on the handshake line")
+}
+
+// ----- SIMULATE -----
+
+const DEFAULT_MAX_TURNS: usize = 200;
+
+struct Config {
+    map_file: String,
+    max_turns: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Map {
+    planets: Vec<Planet>,
+}
+
+fn configured_simulation(args: &[String]) -> Option<Config> {
+    if !args.iter().any(|arg| arg == "--simulate") {
+        return None;
+    }
+
+    let map_file = args.windows(2)
+        .find(|window| window[0] == "--map")
+        .map(|window| window[1].clone())
+        .expect("--simulate requires --map <file>");
+    let max_turns = args.windows(2)
+        .find(|window| window[0] == "--max-turns")
+        .and_then(|window| window[1].parse().ok())
+        .unwrap_or(DEFAULT_MAX_TURNS);
+
+    Some(Config { map_file, max_turns })
+}
+
+fn load_map(config: &Config) -> GameState {
+    let contents = std::fs::read_to_string(&config.map_file).expect("Could not read map file");
+    let map: Map = serde_json::from_str(&contents).expect("Could not deserialize map");
+    GameState { planets: map.planets, expeditions: vec![] }
+}
+
+fn active_players(state: &GameState) -> Vec<usize> {
+    let mut owners: Vec<usize> = state.planets.iter().filter_map(|p| p.owner).collect();
+    owners.sort();
+    owners.dedup();
+    owners
+}
+
+// Advances a self-play simulation by one turn: calls plan_turn for every
+// player still on the board (each blind to the others' moves this turn, same
+// as the real protocol), turns their Moves into Expeditions with the real
+// travel time, then steps the world with advance_turn.
+fn step_simulation(state: &GameState, depth: usize) -> GameState {
+    let mut turn_state = state.clone();
+    for player_id in active_players(state) {
+        let turn = plan_turn(state, player_id, depth);
+        turn_state = apply_turn(&turn_state, player_id, &turn);
+    }
+    advance_turn(&turn_state)
+}
+
+// Runs the bot against itself on an offline map, stepping turn by turn until
+// one player remains or max_turns is reached, printing each GameState as a
+// JSON line so two heuristic versions can be pit against each other from a
+// reproducible map.
+fn run_simulation(config: Config, depth: usize) {
+    let mut state = load_map(&config);
+    let mut turn_number = 0;
 
-            used_planets.insert(source.name.to_string());
+    loop {
+        println!("{}", serde_json::to_string(&state).expect("Could not serialize"));
 
-            best_move = iproduct!(
-                my_planets.iter().filter(|x| !used_planets.contains(&x.name)),
-                other_planets.iter()
-            ).map(|(s,d)| (s, d, score(s, d, state)))
-             .filter(|(_,_,(_, sc))| *sc != 0)
-             .min_by_key(|x| (*x).2);
+        if active_players(&state).len() <= 1 || turn_number >= config.max_turns {
+            break;
         }
-        Turn { moves: moves }
+
+        state = step_simulation(&state, depth);
+        turn_number += 1;
     }
 }
 
 // ----- MAIN -----
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let depth = configured_search_depth();
+
+    if let Some(config) = configured_simulation(&args) {
+        run_simulation(config, depth);
+        return;
+    }
+
     let stdin = io::stdin();
+    let player_id = configured_player_id(&args, &stdin);
+
     for line in stdin.lock().lines() {
         let state: GameState = serde_json::from_str(line.expect("Could not deserialize").as_str()).unwrap();
-        let turn: Turn = next_move(&state);
+        let turn: Turn = plan_turn(&state, player_id, depth);
         println!("{}", serde_json::to_string(&turn).expect("Could not serialize"));
     }
 }
@@ -266,8 +846,8 @@ fn test_score_fewer_ships() {
         expeditions: vec![],
     };
 
-    let mudhut_score = score(&homebase, &mudhut, &dummy_gamestate);
-    let fort_score   = score(&homebase, &fort,   &dummy_gamestate);
+    let mudhut_score = score_for(1, &homebase, &mudhut, &dummy_gamestate);
+    let fort_score   = score_for(1, &homebase, &fort,   &dummy_gamestate);
     assert!(mudhut_score.1 < fort_score.1);
 }
 
@@ -295,6 +875,384 @@ fn test_score_empty_planet() {
         expeditions: vec![],
     };
 
-    let empty_score = score(&homebase, &empty, &dummy_gamestate).1;
+    let empty_score = score_for(1, &homebase, &empty, &dummy_gamestate).1;
     assert!(empty_score > 0);
 }
+
+#[test]
+fn test_advance_turn() {
+    let homebase = Planet {
+                name: "Homebase".to_string(),
+                owner: Some(1),
+                ship_count: 10,
+                x: 0.0,
+                y: 0.0
+            };
+    let contested = Planet {
+                name: "Contested".to_string(),
+                owner: Some(2),
+                ship_count: 5,
+                x: 1.0,
+                y: 0.0
+            };
+    let neutral = Planet {
+                name: "Neutral".to_string(),
+                owner: None,
+                ship_count: 3,
+                x: 2.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![
+            homebase.clone(),
+            contested.clone(),
+            neutral.clone(),
+        ],
+        expeditions: vec![
+            Expedition {
+                id: 1,
+                origin: "Homebase".to_string(),
+                destination: "Contested".to_string(),
+                owner: 1,
+                ship_count: 10,
+                turns_remaining: 1,
+            },
+            Expedition {
+                id: 2,
+                origin: "Homebase".to_string(),
+                destination: "Neutral".to_string(),
+                owner: 1,
+                ship_count: 1,
+                turns_remaining: 2,
+            },
+        ],
+    };
+
+    let next_state = advance_turn(&gamestate);
+
+    let homebase_after = next_state.planets.iter().find(|p| p.name == "Homebase").unwrap();
+    assert_eq!(homebase_after.owner, Some(1));
+    assert_eq!(homebase_after.ship_count, 11);
+
+    // Attacker (10, growing defender counted at 6) wins by 4 ships.
+    let contested_after = next_state.planets.iter().find(|p| p.name == "Contested").unwrap();
+    assert_eq!(contested_after.owner, Some(1));
+    assert_eq!(contested_after.ship_count, 4);
+
+    // Neutral planet doesn't grow and has no arrivals yet.
+    let neutral_after = next_state.planets.iter().find(|p| p.name == "Neutral").unwrap();
+    assert_eq!(neutral_after.owner, None);
+    assert_eq!(neutral_after.ship_count, 3);
+
+    let remaining: Vec<&Expedition> = next_state.expeditions.iter().filter(|e| e.id == 2).collect();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].turns_remaining, 1);
+    assert!(next_state.expeditions.iter().all(|e| e.id != 1));
+}
+
+#[test]
+fn test_advance_turn_does_not_panic_on_already_arrived_expedition() {
+    // A zero-distance dispatch (same-coordinate origin/destination) produces
+    // an expedition with turns_remaining == 0 before it has even been through
+    // advance_turn once; aging it further must saturate instead of underflowing.
+    let homebase = Planet {
+                name: "Homebase".to_string(),
+                owner: Some(1),
+                ship_count: 10,
+                x: 0.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![homebase.clone()],
+        expeditions: vec![
+            Expedition {
+                id: 1,
+                origin: "Homebase".to_string(),
+                destination: "Homebase".to_string(),
+                owner: 1,
+                ship_count: 5,
+                turns_remaining: 0,
+            },
+        ],
+    };
+
+    let next_state = advance_turn(&gamestate);
+
+    assert!(next_state.expeditions.is_empty());
+}
+
+#[test]
+fn test_simulate_arrivals_simultaneous_multi_fleet() {
+    let battlefield = Planet {
+                name: "Planeet Battlefield".to_string(),
+                owner: Some(1),
+                ship_count: 5,
+                x: 0.0,
+                y: 0.0
+            };
+    let dummy_gamestate: GameState = GameState {
+        planets: vec![battlefield.clone()],
+        expeditions: vec![
+            Expedition {
+                destination: "Planeet Battlefield".to_string(),
+                id: 1,
+                origin: "Elsewhere".to_string(),
+                owner: 2,
+                ship_count: 4,
+                turns_remaining: 3,
+            },
+            Expedition {
+                destination: "Planeet Battlefield".to_string(),
+                id: 2,
+                origin: "Elsewhere".to_string(),
+                owner: 3,
+                ship_count: 10,
+                turns_remaining: 3,
+            },
+        ],
+    };
+
+    // Defender (5+3 growth=8) loses to owner 3's 10, owner 2's 4 is third.
+    // Resolving pairwise in arrival order would wrongly have owner 2 or 3
+    // fight the defender one-on-one instead of all three at once.
+    assert_eq!((3, 2), simulate_arrivals(&battlefield, &dummy_gamestate));
+}
+
+#[test]
+fn test_plan_turn_captures_undefended_planet_as_any_player() {
+    let homebase = Planet {
+                name: "Homebase".to_string(),
+                owner: Some(2),
+                ship_count: 20,
+                x: 0.0,
+                y: 0.0
+            };
+    let target = Planet {
+                name: "Target".to_string(),
+                owner: None,
+                ship_count: 1,
+                x: 1.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![homebase.clone(), target.clone()],
+        expeditions: vec![],
+    };
+
+    // Player 2 plans the capture correctly, even though player 1 owns nothing here.
+    let turn = plan_turn(&gamestate, 2, 1);
+
+    assert_eq!(turn.moves.len(), 1);
+    assert_eq!(turn.moves[0].origin, "Homebase");
+    assert_eq!(turn.moves[0].destination, "Target");
+}
+
+#[test]
+fn test_defensive_moves_reinforces_threatened_planet() {
+    let homebase = Planet {
+                name: "Homebase".to_string(),
+                owner: Some(1),
+                ship_count: 50,
+                x: 0.0,
+                y: 0.0
+            };
+    let outpost = Planet {
+                name: "Outpost".to_string(),
+                owner: Some(1),
+                ship_count: 1,
+                x: 10.0,
+                y: 0.0
+            };
+    let near = Planet {
+                name: "Near".to_string(),
+                owner: Some(1),
+                ship_count: 10,
+                x: 12.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![homebase.clone(), outpost.clone(), near.clone()],
+        expeditions: vec![
+            Expedition {
+                id: 1,
+                origin: "Enemy".to_string(),
+                destination: "Outpost".to_string(),
+                owner: 2,
+                ship_count: 5,
+                turns_remaining: 3,
+            },
+        ],
+    };
+
+    let moves = defensive_moves(&gamestate, 1);
+
+    // Homebase is too far away to arrive in time (distance 10 > 3 turns);
+    // Near is the only reinforcer that can reach Outpost before the attack lands.
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].origin, "Near");
+    assert_eq!(moves[0].destination, "Outpost");
+    assert_eq!(moves[0].ship_count, 2);
+}
+
+#[test]
+fn test_defensive_moves_uses_earliest_wave_as_the_deadline() {
+    let outpost = Planet {
+                name: "Outpost".to_string(),
+                owner: Some(1),
+                ship_count: 1,
+                x: 0.0,
+                y: 0.0
+            };
+    let far = Planet {
+                name: "Far".to_string(),
+                owner: Some(1),
+                ship_count: 50,
+                x: 5.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![outpost.clone(), far.clone()],
+        expeditions: vec![
+            // Small first wave flips Outpost on turn 2...
+            Expedition {
+                id: 1,
+                origin: "Enemy".to_string(),
+                destination: "Outpost".to_string(),
+                owner: 2,
+                ship_count: 3,
+                turns_remaining: 2,
+            },
+            // ...a much bigger second wave lands on turn 8, but by then the
+            // planet is already lost; it must not inflate the deadline.
+            Expedition {
+                id: 2,
+                origin: "Enemy".to_string(),
+                destination: "Outpost".to_string(),
+                owner: 2,
+                ship_count: 20,
+                turns_remaining: 8,
+            },
+        ],
+    };
+
+    let moves = defensive_moves(&gamestate, 1);
+
+    // Far is 5 turns away: too slow to beat the turn-2 flip, even though it
+    // would have arrived well before the inflated turn-8 "deadline".
+    assert!(moves.is_empty());
+}
+
+#[test]
+fn test_hungarian_assignment_picks_min_cost_matching() {
+    // Row 0 prefers column 1 (1 < 4), row 1 prefers column 1 too (0 < 2),
+    // but they can't share it: the optimal matching is row0->col1, row1->col0
+    // (cost 1 + 2 = 3), beating row0->col0, row1->col1 (cost 4 + 0 = 4).
+    let cost = vec![
+        vec![4, 1],
+        vec![2, 0],
+    ];
+
+    assert_eq!(vec![1, 0], hungarian_assignment(&cost));
+}
+
+#[test]
+fn test_assignment_turn_lets_one_source_fund_multiple_targets() {
+    let homebase = Planet {
+                name: "Homebase".to_string(),
+                owner: Some(1),
+                ship_count: 10,
+                x: 0.0,
+                y: 0.0
+            };
+    let alpha = Planet {
+                name: "Alpha".to_string(),
+                owner: None,
+                ship_count: 1,
+                x: 1.0,
+                y: 0.0
+            };
+    let beta = Planet {
+                name: "Beta".to_string(),
+                owner: None,
+                ship_count: 1,
+                x: 2.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![homebase.clone(), alpha.clone(), beta.clone()],
+        expeditions: vec![],
+    };
+
+    let turn = assignment_turn(&gamestate, 1);
+
+    assert_eq!(turn.moves.len(), 2);
+    assert!(turn.moves.iter().all(|mv| mv.origin == "Homebase"));
+    assert!(turn.moves.iter().any(|mv| mv.destination == "Alpha"));
+    assert!(turn.moves.iter().any(|mv| mv.destination == "Beta"));
+}
+
+#[test]
+fn test_active_players_dedups_and_ignores_neutral() {
+    let gamestate: GameState = GameState {
+        planets: vec![
+            Planet { name: "A".to_string(), owner: Some(2), ship_count: 1, x: 0.0, y: 0.0 },
+            Planet { name: "B".to_string(), owner: Some(1), ship_count: 1, x: 1.0, y: 0.0 },
+            Planet { name: "C".to_string(), owner: Some(2), ship_count: 1, x: 2.0, y: 0.0 },
+            Planet { name: "D".to_string(), owner: None,    ship_count: 1, x: 3.0, y: 0.0 },
+        ],
+        expeditions: vec![],
+    };
+
+    assert_eq!(vec![1, 2], active_players(&gamestate));
+}
+
+#[test]
+fn test_configured_simulation_parses_flags() {
+    let args: Vec<String> = vec![
+        "anna".to_string(),
+        "--simulate".to_string(),
+        "--map".to_string(),
+        "map.json".to_string(),
+        "--max-turns".to_string(),
+        "42".to_string(),
+    ];
+
+    let config = configured_simulation(&args).expect("--simulate should produce a Config");
+    assert_eq!(config.map_file, "map.json");
+    assert_eq!(config.max_turns, 42);
+}
+
+#[test]
+fn test_configured_simulation_is_none_without_the_flag() {
+    let args: Vec<String> = vec!["anna".to_string()];
+    assert!(configured_simulation(&args).is_none());
+}
+
+#[test]
+fn test_step_simulation_captures_an_undefended_neighbor() {
+    let homebase = Planet {
+                name: "Homebase".to_string(),
+                owner: Some(1),
+                ship_count: 20,
+                x: 0.0,
+                y: 0.0
+            };
+    let target = Planet {
+                name: "Target".to_string(),
+                owner: None,
+                ship_count: 1,
+                x: 1.0,
+                y: 0.0
+            };
+    let gamestate: GameState = GameState {
+        planets: vec![homebase.clone(), target.clone()],
+        expeditions: vec![],
+    };
+
+    // Distance 1 means the dispatched expedition both arrives and resolves
+    // within this single step.
+    let next_state = step_simulation(&gamestate, 1);
+
+    let target_after = next_state.planets.iter().find(|p| p.name == "Target").unwrap();
+    assert_eq!(target_after.owner, Some(1));
+}